@@ -0,0 +1,35 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use thiserror::Error;
+
+/// The type returned by the routing message-handling API.
+pub type Result<T, E = RoutingError> = std::result::Result<T, E>;
+
+/// Internal error type.
+#[derive(Debug, Error)]
+pub enum RoutingError {
+    /// A message's signature didn't validate against the key it claims to be signed by.
+    #[error("failed signature")]
+    FailedSignature,
+    /// A message's proof isn't trusted and `VerifyStatus::require_full` was called on it.
+    #[error("untrusted message")]
+    UntrustedMessage,
+    /// The message's header named a protocol version or wire format this node doesn't support.
+    #[error("unsupported message protocol version")]
+    UnsupportedMessageVersion,
+    /// Failed to (de)serialise a bincode-framed message body.
+    #[error("bincode error: {0}")]
+    Bincode(#[from] bincode::Error),
+    /// Failed to serialise a MessagePack-framed message body.
+    #[error("message pack encode error: {0}")]
+    MessagePackEncode(#[from] rmp_serde::encode::Error),
+    /// Failed to deserialise a MessagePack-framed message body.
+    #[error("message pack decode error: {0}")]
+    MessagePackDecode(#[from] rmp_serde::decode::Error),
+}
@@ -0,0 +1,50 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{signature_aggregator::SignatureShare, Variant};
+use crate::location::DstLocation;
+use serde::{Deserialize, Serialize};
+use xor_name::{Prefix, XorName};
+
+/// A section-sourced message before it has been signed.
+///
+/// This carries everything needed to derive the bytes that elders sign with their BLS secret
+/// key share; it has no signature or proof attached yet.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub struct PlainMessage {
+    /// Prefix of the section signing this message.
+    pub src: Prefix<XorName>,
+    /// Destination location.
+    pub dst: DstLocation,
+    /// Destination's knowledge of our section key, if any.
+    pub dst_key: Option<bls::PublicKey>,
+    /// The message body.
+    pub variant: Variant,
+}
+
+/// A `PlainMessage` plus one elder's signature share on it.
+///
+/// Elders gossip these to each other; the `signature_aggregator` module combines enough of them
+/// into a single `SrcAuthority::Section` signature.
+#[derive(Clone, Debug)]
+pub struct AccumulatingMessage {
+    /// The message being signed.
+    pub content: PlainMessage,
+    /// This elder's share of the section signature.
+    pub proof_share: SignatureShare,
+}
+
+impl AccumulatingMessage {
+    /// Create a new accumulating message from its content and one share.
+    pub fn new(content: PlainMessage, proof_share: SignatureShare) -> Self {
+        Self {
+            content,
+            proof_share,
+        }
+    }
+}
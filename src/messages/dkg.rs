@@ -0,0 +1,83 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Distributed key generation: lets a section jointly produce a fresh `bls::PublicKeySet` and
+//! secret-key-shares without a trusted dealer, driven by membership churn. A completed round's
+//! key is agreed and signed, then folded into the section's `SectionProofChain` so later
+//! `SrcAuthority::Section` messages can sign with the rotated key while the old one remains
+//! verifiable.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+use xor_name::XorName;
+
+/// Identifies one DKG round: the elders taking part plus a generation counter bumped every time
+/// the participant set changes, so stale messages from a superseded round are unambiguous.
+#[derive(Clone, Eq, PartialEq, Ord, PartialOrd, Serialize, Deserialize, Hash, Debug)]
+pub struct DkgKey {
+    elders: BTreeSet<XorName>,
+    generation: u64,
+}
+
+impl DkgKey {
+    /// Create a key for a round among `elders` at the given `generation`.
+    pub fn new(elders: BTreeSet<XorName>, generation: u64) -> Self {
+        Self { elders, generation }
+    }
+
+    /// The elders taking part in this round.
+    pub fn elders(&self) -> &BTreeSet<XorName> {
+        &self.elders
+    }
+}
+
+/// A value proposed for section-wide agreement.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub enum Proposal {
+    /// The section key produced by a completed DKG round should be adopted.
+    OurKey {
+        /// The round that produced `key`.
+        dkg_key: DkgKey,
+        /// The new section public key.
+        key: bls::PublicKey,
+    },
+}
+
+/// A `Proposal` that has reached section-wide agreement, together with the combined signature
+/// that proves it.
+#[derive(Clone, Debug)]
+pub struct SectionSigned<T> {
+    /// The agreed-on value.
+    pub value: T,
+    /// Combined section signature, made with the key *preceding* the rotation. For
+    /// `Proposal::OurKey` it is over the bincode-serialised new `key` alone, so the new key can be
+    /// appended to the proof chain as an entry signed by its predecessor.
+    pub signature: bls::Signature,
+}
+
+impl SectionSigned<Proposal> {
+    /// The `(new_key, signature)` pair to append to a `SectionProofChain`.
+    pub fn key_rotation(self) -> (bls::PublicKey, bls::Signature) {
+        let Proposal::OurKey { key, .. } = self.value;
+        (key, self.signature)
+    }
+}
+
+/// Sent by a DKG participant when its round has stalled (e.g. a participant went offline before
+/// completing), so the remaining elders can agree to abort and retry with a reduced set instead
+/// of deadlocking forever.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub struct DkgFailureSigned {
+    /// The round that failed.
+    pub dkg_key: DkgKey,
+    /// Elders that acknowledge the failure.
+    pub acknowledgers: BTreeSet<XorName>,
+    /// Combined signature of the acknowledgers over `(dkg_key, acknowledgers)`, so a single
+    /// dishonest elder can't fake a failure and force a retry on its own.
+    pub signature: bls::Signature,
+}
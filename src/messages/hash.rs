@@ -0,0 +1,36 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+use tiny_keccak::{Hasher, Sha3};
+
+/// Hash of a message, used to deduplicate accumulating shares and as a map key.
+#[derive(Default, Clone, Copy, Eq, PartialEq, Ord, PartialOrd, Hash, Serialize, Deserialize)]
+pub struct MessageHash([u8; 32]);
+
+impl MessageHash {
+    /// Compute the hash of the given bytes.
+    pub fn from_bytes(bytes: &[u8]) -> Self {
+        let mut hasher = Sha3::v256();
+        let mut hash = [0; 32];
+        hasher.update(bytes);
+        hasher.finalize(&mut hash);
+        Self(hash)
+    }
+}
+
+impl Debug for MessageHash {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        write!(
+            formatter,
+            "{:02x}{:02x}{:02x}..",
+            self.0[0], self.0[1], self.0[2]
+        )
+    }
+}
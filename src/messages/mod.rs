@@ -7,15 +7,24 @@
 // permissions and limitations relating to use of the SAFE Network Software.
 
 mod accumulating_message;
+mod dkg;
 mod hash;
+mod proof_chain;
+mod signature_aggregator;
 mod src_authority;
 mod variant;
 
 pub use self::{
     accumulating_message::{AccumulatingMessage, PlainMessage},
+    dkg::{DkgFailureSigned, DkgKey, Proposal, SectionSigned},
     hash::MessageHash,
+    proof_chain::SectionProofChain,
+    signature_aggregator::{SignatureAggregator, SignatureShare},
     src_authority::SrcAuthority,
-    variant::{BootstrapResponse, JoinRequest, Variant},
+    variant::{
+        BootstrapResponse, JoinAsRelocatedRequest, JoinAsRelocatedResponse, JoinRejectionReason,
+        JoinRequest, JoinResponse, RelocateDetails, RelocateProof, ResourceProofResponse, Variant,
+    },
 };
 use crate::{
     error::{Result, RoutingError},
@@ -58,8 +67,10 @@ pub struct Message {
 impl Message {
     /// Deserialize the message. Only called on message receipt.
     pub(crate) fn from_bytes(bytes: &Bytes) -> Result<Self> {
-        let mut msg: Message = bincode::deserialize(&bytes[..])?;
-        let signed_bytes = serialize_for_signing(&msg.dst, msg.dst_key.as_ref(), &msg.variant)?;
+        let (format, body) = read_header(&bytes[..])?;
+        let mut msg: Message = format.deserialize(body)?;
+        let signed_bytes =
+            serialize_for_signing(format, &msg.dst, msg.dst_key.as_ref(), &msg.variant)?;
         match msg.src.clone() {
             SrcAuthority::Node {
                 public_id,
@@ -76,8 +87,7 @@ impl Message {
             SrcAuthority::Section {
                 signature, proof, ..
             } => {
-                // FIXME Assumes the nodes proof last key is the one signing this message
-                if proof.last_key().verify(&signature, &signed_bytes) {
+                if proof.validate().is_ok() && proof.last_key().verify(&signature, &signed_bytes) {
                     msg.serialized = bytes.clone();
                     msg.hash = MessageHash::from_bytes(bytes);
                     Ok(msg)
@@ -93,12 +103,13 @@ impl Message {
         self.serialized.clone()
     }
 
-    /// Creates a signed message where signature is assumed valid.
+    /// Creates a signed message where signature is assumed valid, framed using `format`.
     fn new_signed(
         src: SrcAuthority,
         dst: DstLocation,
         dst_key: Option<bls::PublicKey>,
         variant: Variant,
+        format: WireFormat,
     ) -> Result<Message> {
         let mut msg = Message {
             dst,
@@ -108,27 +119,43 @@ impl Message {
             serialized: Default::default(),
             hash: Default::default(),
         };
-        let bytes: Bytes = bincode::serialize(&msg)?.into();
+        let body = format.serialize(&msg)?;
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.len());
+        bytes.extend_from_slice(&write_header(format));
+        bytes.extend_from_slice(&body);
+        let bytes: Bytes = bytes.into();
         msg.serialized = bytes.clone();
         msg.hash = MessageHash::from_bytes(&bytes);
         Ok(msg)
     }
 
-    /// Creates a signed message from single node.
+    /// Creates a signed message from single node, framed as bincode.
     pub(crate) fn single_src(
         src: &FullId,
         dst: DstLocation,
         dst_key: Option<bls::PublicKey>,
         variant: Variant,
     ) -> Result<Self> {
-        let serialized = serialize_for_signing(&dst, dst_key.as_ref(), &variant)?;
+        Self::single_src_with_format(src, dst, dst_key, variant, WireFormat::Bincode)
+    }
+
+    /// Creates a signed message from single node, framed using `format` - e.g. `MessagePack` for
+    /// peers that have negotiated it as their wire encoding.
+    pub(crate) fn single_src_with_format(
+        src: &FullId,
+        dst: DstLocation,
+        dst_key: Option<bls::PublicKey>,
+        variant: Variant,
+        format: WireFormat,
+    ) -> Result<Self> {
+        let serialized = serialize_for_signing(format, &dst, dst_key.as_ref(), &variant)?;
         let signature = src.sign(&serialized);
         let src = SrcAuthority::Node {
             public_id: *src.public_id(),
             signature,
         };
 
-        Self::new_signed(src, dst, dst_key, variant)
+        Self::new_signed(src, dst, dst_key, variant, format)
     }
 
     /// Verify this message is properly signed and trusted.
@@ -190,14 +217,19 @@ pub enum VerifyStatus {
     // The message trust and integrity cannot be verified because it's proof is not trusted by us,
     // even though it is valid. The message should be relayed to other nodes who might be able to
     // verify it.
-    Unknown,
+    Unknown {
+        // The most recent key in the message's proof chain that we already trust, if any. The
+        // relay logic should ask the sender for a chain extended from this key onwards, rather
+        // than re-requesting the whole thing.
+        truncation_point: Option<bls::PublicKey>,
+    },
 }
 
 impl VerifyStatus {
     pub fn require_full(self) -> Result<(), RoutingError> {
         match self {
             Self::Full => Ok(()),
-            Self::Unknown => Err(RoutingError::UntrustedMessage),
+            Self::Unknown { .. } => Err(RoutingError::UntrustedMessage),
         }
     }
 }
@@ -235,9 +267,85 @@ pub enum MessageStatus {
 }
 
 fn serialize_for_signing(
+    format: WireFormat,
     dst: &DstLocation,
     dst_key: Option<&bls::PublicKey>,
     variant: &Variant,
 ) -> Result<Vec<u8>> {
-    Ok(bincode::serialize(&(dst, dst_key, variant))?)
+    // The header is included in what gets signed, not just what gets sent, so a message can't be
+    // re-framed under a different protocol version/format after the fact without invalidating
+    // the signature.
+    let mut bytes = write_header(format).to_vec();
+    bytes.extend_from_slice(&format.serialize(&(dst, dst_key, variant))?);
+    Ok(bytes)
+}
+
+/// Body encoding used after the wire header. Negotiated by the header's format tag so nodes can
+/// be upgraded to a new encoding without breaking ones still running the old one.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub(crate) enum WireFormat {
+    Bincode,
+    MessagePack,
+}
+
+impl WireFormat {
+    const BINCODE_TAG: u8 = 0;
+    const MESSAGE_PACK_TAG: u8 = 1;
+
+    fn tag(self) -> u8 {
+        match self {
+            Self::Bincode => Self::BINCODE_TAG,
+            Self::MessagePack => Self::MESSAGE_PACK_TAG,
+        }
+    }
+
+    fn from_tag(tag: u8) -> Result<Self> {
+        match tag {
+            Self::BINCODE_TAG => Ok(Self::Bincode),
+            Self::MESSAGE_PACK_TAG => Ok(Self::MessagePack),
+            _ => Err(RoutingError::UnsupportedMessageVersion),
+        }
+    }
+
+    fn serialize<T: serde::Serialize>(self, value: &T) -> Result<Vec<u8>> {
+        match self {
+            Self::Bincode => Ok(bincode::serialize(value)?),
+            Self::MessagePack => Ok(rmp_serde::to_vec(value)?),
+        }
+    }
+
+    fn deserialize<T: serde::de::DeserializeOwned>(self, bytes: &[u8]) -> Result<T> {
+        match self {
+            Self::Bincode => Ok(bincode::deserialize(bytes)?),
+            Self::MessagePack => Ok(rmp_serde::from_read_ref(bytes)?),
+        }
+    }
+}
+
+/// `MAGIC` (so a stray non-`Message` payload is rejected immediately), a one-byte format tag,
+/// then a little-endian `u16` protocol version - all ahead of the framed body.
+const MAGIC: [u8; 4] = *b"SAFE";
+const PROTOCOL_VERSION: u16 = 1;
+const HEADER_LEN: usize = MAGIC.len() + 1 + 2;
+
+fn write_header(format: WireFormat) -> [u8; HEADER_LEN] {
+    let mut header = [0; HEADER_LEN];
+    header[..4].copy_from_slice(&MAGIC);
+    header[4] = format.tag();
+    header[5..7].copy_from_slice(&PROTOCOL_VERSION.to_le_bytes());
+    header
+}
+
+fn read_header(bytes: &[u8]) -> Result<(WireFormat, &[u8])> {
+    if bytes.len() < HEADER_LEN || bytes[..4] != MAGIC {
+        return Err(RoutingError::UnsupportedMessageVersion);
+    }
+
+    let format = WireFormat::from_tag(bytes[4])?;
+    let version = u16::from_le_bytes([bytes[5], bytes[6]]);
+    if version != PROTOCOL_VERSION {
+        return Err(RoutingError::UnsupportedMessageVersion);
+    }
+
+    Ok((format, &bytes[HEADER_LEN..]))
 }
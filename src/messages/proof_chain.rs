@@ -0,0 +1,174 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use crate::error::{Result, RoutingError};
+use serde::{Deserialize, Serialize};
+
+/// Secured linked list of the keys a section has used to sign `SrcAuthority::Section` messages.
+///
+/// Each entry is a `(prev_key, new_key)` rotation, proved by `prev_key`'s signature over
+/// `new_key`, starting from a genesis key. That makes the whole chain tamper-evident and
+/// independently verifiable by anyone who holds it, rather than requiring the receiver to simply
+/// trust that whichever key signed the message is legitimate.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub struct SectionProofChain {
+    genesis_key: bls::PublicKey,
+    // Each link is the newly rotated-in key plus the signature of the *previous* key (the
+    // previous entry's key, or `genesis_key` for the first link) over it.
+    links: Vec<(bls::PublicKey, bls::Signature)>,
+}
+
+impl SectionProofChain {
+    /// Start a new chain at the given genesis key.
+    pub fn new(genesis_key: bls::PublicKey) -> Self {
+        Self {
+            genesis_key,
+            links: Vec::new(),
+        }
+    }
+
+    /// The key that should have signed the most recent message.
+    pub fn last_key(&self) -> &bls::PublicKey {
+        self.links
+            .last()
+            .map(|(key, _)| key)
+            .unwrap_or(&self.genesis_key)
+    }
+
+    /// Whether `key` appears anywhere in the chain.
+    pub fn has_key(&self, key: &bls::PublicKey) -> bool {
+        self.keys().any(|k| k == key)
+    }
+
+    /// All keys in the chain, oldest (genesis) first.
+    pub fn keys(&self) -> impl DoubleEndedIterator<Item = &bls::PublicKey> {
+        std::iter::once(&self.genesis_key).chain(self.links.iter().map(|(key, _)| key))
+    }
+
+    /// The most recent key in the chain that also appears in `trusted_keys` - the furthest point
+    /// from which trust in this chain can be extended. `None` if the chain shares no key at all
+    /// with `trusted_keys`, meaning the caller would need the chain extended all the way back to
+    /// (at least) genesis before it can be trusted.
+    pub fn last_trusted_key<'a, I>(&self, trusted_keys: I) -> Option<&bls::PublicKey>
+    where
+        I: IntoIterator<Item = &'a bls::PublicKey>,
+    {
+        let trusted: std::collections::HashSet<_> = trusted_keys.into_iter().collect();
+        self.keys().rev().find(|key| trusted.contains(key))
+    }
+
+    /// Check that every link in the chain is validly signed by its predecessor, starting from
+    /// genesis. This must be done on any chain received from the network before trusting it -
+    /// a chain is only as good as its weakest link.
+    pub fn validate(&self) -> Result<()> {
+        let mut prev_key = &self.genesis_key;
+        for (key, signature) in &self.links {
+            let bytes = bincode::serialize(key)?;
+            if !prev_key.verify(signature, &bytes) {
+                return Err(RoutingError::FailedSignature);
+            }
+            prev_key = key;
+        }
+        Ok(())
+    }
+
+    /// Append a key rotated in by DKG + agreement. `signature` must be the current last key's
+    /// signature over `new_key`; the link is rejected rather than appended if it doesn't verify,
+    /// so the chain can never grow an invalid link.
+    pub fn push(&mut self, new_key: bls::PublicKey, signature: bls::Signature) -> Result<()> {
+        let bytes = bincode::serialize(&new_key)?;
+        if !self.last_key().verify(&signature, &bytes) {
+            return Err(RoutingError::FailedSignature);
+        }
+
+        self.links.push((new_key, signature));
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn gen_key() -> bls::SecretKey {
+        bls::SecretKey::random()
+    }
+
+    #[test]
+    fn validate_accepts_a_chain_with_valid_links() {
+        let genesis_sk = gen_key();
+        let mut chain = SectionProofChain::new(genesis_sk.public_key());
+
+        let key1_sk = gen_key();
+        let signature = genesis_sk.sign(&bincode::serialize(&key1_sk.public_key()).unwrap());
+        chain.push(key1_sk.public_key(), signature).unwrap();
+
+        assert!(chain.validate().is_ok());
+    }
+
+    #[test]
+    fn validate_rejects_a_chain_with_a_tampered_link() {
+        let genesis_sk = gen_key();
+        let mut chain = SectionProofChain::new(genesis_sk.public_key());
+
+        let key1_sk = gen_key();
+        let signature = genesis_sk.sign(&bincode::serialize(&key1_sk.public_key()).unwrap());
+        chain.push(key1_sk.public_key(), signature).unwrap();
+
+        // Splice in a link signed by the wrong key, bypassing `push`'s own check.
+        let other_sk = gen_key();
+        let forged_signature = other_sk.sign(&bincode::serialize(&key1_sk.public_key()).unwrap());
+        chain.links[0].1 = forged_signature;
+
+        assert!(chain.validate().is_err());
+    }
+
+    #[test]
+    fn push_rejects_a_link_not_signed_by_the_current_last_key() {
+        let genesis_sk = gen_key();
+        let mut chain = SectionProofChain::new(genesis_sk.public_key());
+
+        let key1_sk = gen_key();
+        let wrong_sk = gen_key();
+        let signature = wrong_sk.sign(&bincode::serialize(&key1_sk.public_key()).unwrap());
+
+        assert!(chain.push(key1_sk.public_key(), signature).is_err());
+    }
+
+    #[test]
+    fn last_trusted_key_finds_an_ancestor_key_we_trust() {
+        let genesis_sk = gen_key();
+        let mut chain = SectionProofChain::new(genesis_sk.public_key());
+
+        let key1_sk = gen_key();
+        let signature = genesis_sk.sign(&bincode::serialize(&key1_sk.public_key()).unwrap());
+        chain.push(key1_sk.public_key(), signature).unwrap();
+
+        let key2_sk = gen_key();
+        let signature = key1_sk.sign(&bincode::serialize(&key2_sk.public_key()).unwrap());
+        chain.push(key2_sk.public_key(), signature).unwrap();
+
+        // We only trust the genesis key, not the two rotated-in keys.
+        let trusted = [genesis_sk.public_key()];
+        let last_trusted = chain.last_trusted_key(trusted.iter());
+
+        assert_eq!(last_trusted, Some(&genesis_sk.public_key()));
+        assert_ne!(last_trusted, Some(chain.last_key()));
+    }
+
+    #[test]
+    fn last_trusted_key_returns_none_when_we_share_no_key_with_the_chain() {
+        let genesis_sk = gen_key();
+        let chain = SectionProofChain::new(genesis_sk.public_key());
+
+        let unrelated_sk = gen_key();
+        let trusted = [unrelated_sk.public_key()];
+
+        assert_eq!(chain.last_trusted_key(trusted.iter()), None);
+    }
+}
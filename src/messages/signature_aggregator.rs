@@ -0,0 +1,248 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+//! Accumulates BLS signature shares contributed by individual elders into the single section
+//! signature carried by a `SrcAuthority::Section`, without any elder ever holding the section's
+//! master secret key.
+
+use super::{
+    accumulating_message::AccumulatingMessage,
+    dkg::{Proposal, SectionSigned},
+    hash::MessageHash,
+    proof_chain::SectionProofChain,
+    serialize_for_signing,
+    src_authority::SrcAuthority,
+};
+use crate::error::Result;
+use std::{
+    collections::BTreeMap,
+    time::{Duration, Instant},
+};
+
+/// How long an incomplete accumulator is kept around before being dropped, to bound memory use
+/// by elders that never finish signing (e.g. because the section moved on).
+const EXPIRY: Duration = Duration::from_secs(120);
+
+/// One elder's contribution towards a section signature: their share, tagged with the index of
+/// the secret-key-share that produced it.
+#[derive(Clone, Debug)]
+pub struct SignatureShare {
+    /// Index of the secret-key-share within the section's `PublicKeySet`.
+    pub index: usize,
+    /// The share itself.
+    pub share: bls::SignatureShare,
+}
+
+struct Accumulator {
+    content: super::accumulating_message::PlainMessage,
+    shares: BTreeMap<usize, bls::SignatureShare>,
+    // Set once enough shares have combined, so late shares are recognised and dropped rather
+    // than re-combined.
+    combined: bool,
+    since: Instant,
+}
+
+/// Collects `SignatureShare`s for in-flight section messages, keyed by the hash of the bytes
+/// being signed, and combines them into a full `SrcAuthority::Section` once `threshold + 1`
+/// valid shares have been seen for a message.
+pub struct SignatureAggregator {
+    proof_chain: SectionProofChain,
+    public_key_set: bls::PublicKeySet,
+    accumulators: BTreeMap<MessageHash, Accumulator>,
+}
+
+impl SignatureAggregator {
+    /// Create an aggregator for a section whose most recent key is the last key of
+    /// `proof_chain`, backed by secret-key-shares from `public_key_set`.
+    pub fn new(proof_chain: SectionProofChain, public_key_set: bls::PublicKeySet) -> Self {
+        Self {
+            proof_chain,
+            public_key_set,
+            accumulators: BTreeMap::new(),
+        }
+    }
+
+    /// Add a share towards `msg`'s section signature.
+    ///
+    /// Returns `Ok(Some(_))` once this share completes the message (`threshold + 1` valid
+    /// shares have been combined), `Ok(None)` if more shares are still needed. Shares that don't
+    /// verify against their claimed index are logged and dropped rather than erroring, so one
+    /// faulty elder can't poison the whole accumulator.
+    pub fn add(&mut self, msg: AccumulatingMessage) -> Result<Option<SrcAuthority>> {
+        self.remove_expired();
+
+        let bytes = serialize_for_signing(
+            super::WireFormat::Bincode,
+            &msg.content.dst,
+            msg.content.dst_key.as_ref(),
+            &msg.content.variant,
+        )?;
+        let hash = MessageHash::from_bytes(&bytes);
+
+        let index = msg.proof_share.index;
+        let public_key_share = self.public_key_set.public_key_share(index);
+        if !public_key_share.verify(&msg.proof_share.share, &bytes) {
+            log::debug!("dropping invalid signature share from index {}", index);
+            return Ok(None);
+        }
+
+        let accumulator = self.accumulators.entry(hash).or_insert_with(|| Accumulator {
+            content: msg.content,
+            shares: BTreeMap::new(),
+            combined: false,
+            since: Instant::now(),
+        });
+
+        if accumulator.combined {
+            // The message already has a combined signature - a late, duplicate or
+            // post-completion share is simply ignored.
+            return Ok(None);
+        }
+
+        let _ = accumulator.shares.insert(index, msg.proof_share.share);
+
+        if accumulator.shares.len() <= self.public_key_set.threshold() {
+            return Ok(None);
+        }
+
+        let signature = self
+            .public_key_set
+            .combine_signatures(accumulator.shares.iter().map(|(index, share)| (*index, share)))?;
+
+        if !self.public_key_set.public_key().verify(&signature, &bytes) {
+            log::debug!("combined section signature for {:?} failed to verify", hash);
+            return Ok(None);
+        }
+
+        accumulator.combined = true;
+
+        Ok(Some(SrcAuthority::Section {
+            prefix: accumulator.content.src,
+            signature,
+            proof: self.proof_chain.clone(),
+        }))
+    }
+
+    /// Adopt a section key rotated in by a completed, agreed DKG round: extend the proof chain
+    /// with it and start signing with its secret-key-shares from now on. Messages signed with
+    /// the previous key remain verifiable, since it's still in the chain.
+    pub fn rotate_key(
+        &mut self,
+        new_public_key_set: bls::PublicKeySet,
+        agreed: SectionSigned<Proposal>,
+    ) -> Result<()> {
+        let (new_key, signature) = agreed.key_rotation();
+        self.proof_chain.push(new_key, signature)?;
+        self.public_key_set = new_public_key_set;
+        Ok(())
+    }
+
+    fn remove_expired(&mut self) {
+        let now = Instant::now();
+        self.accumulators
+            .retain(|_, accumulator| now.duration_since(accumulator.since) < EXPIRY);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        location::DstLocation,
+        messages::{PlainMessage, Variant},
+    };
+    use rand::thread_rng;
+    use xor_name::Prefix;
+
+    fn new_aggregator(threshold: usize) -> (bls::SecretKeySet, SignatureAggregator) {
+        let secret_key_set = bls::SecretKeySet::random(threshold, &mut thread_rng());
+        let public_key_set = secret_key_set.public_keys();
+        let proof_chain = SectionProofChain::new(public_key_set.public_key());
+        let aggregator = SignatureAggregator::new(proof_chain, public_key_set);
+        (secret_key_set, aggregator)
+    }
+
+    fn share_for(
+        secret_key_set: &bls::SecretKeySet,
+        index: usize,
+        msg: &PlainMessage,
+    ) -> AccumulatingMessage {
+        let bytes = serialize_for_signing(
+            super::super::WireFormat::Bincode,
+            &msg.dst,
+            msg.dst_key.as_ref(),
+            &msg.variant,
+        )
+        .unwrap();
+        let share = secret_key_set.secret_key_share(index).sign(&bytes);
+        AccumulatingMessage::new(msg.clone(), SignatureShare { index, share })
+    }
+
+    fn plain_message() -> PlainMessage {
+        PlainMessage {
+            src: Prefix::default(),
+            dst: DstLocation::Direct,
+            dst_key: None,
+            variant: Variant::Ping,
+        }
+    }
+
+    #[test]
+    fn add_combines_once_threshold_is_exceeded() {
+        let threshold = 2;
+        let (secret_key_set, mut aggregator) = new_aggregator(threshold);
+        let msg = plain_message();
+
+        // `threshold` shares are not enough on their own...
+        for index in 0..threshold {
+            let share = share_for(&secret_key_set, index, &msg);
+            assert!(aggregator.add(share).unwrap().is_none());
+        }
+
+        // ...but the next one brings it to `threshold + 1` and combines.
+        let share = share_for(&secret_key_set, threshold, &msg);
+        assert!(aggregator.add(share).unwrap().is_some());
+    }
+
+    #[test]
+    fn add_drops_a_share_with_an_invalid_signature() {
+        let (secret_key_set, mut aggregator) = new_aggregator(2);
+        let msg = plain_message();
+
+        let mut share = share_for(&secret_key_set, 0, &plain_message());
+        // Sign over a different message than the one actually carried, so the share no longer
+        // verifies against its claimed index.
+        let other_bytes = serialize_for_signing(
+            super::super::WireFormat::Bincode,
+            &msg.dst,
+            msg.dst_key.as_ref(),
+            &Variant::UserMessage(vec![1]),
+        )
+        .unwrap();
+        share.proof_share.share = secret_key_set.secret_key_share(0).sign(&other_bytes);
+
+        assert_eq!(aggregator.add(share).unwrap(), None);
+        assert!(aggregator.accumulators.is_empty());
+    }
+
+    #[test]
+    fn add_ignores_a_share_received_after_the_message_already_combined() {
+        let threshold = 1;
+        let (secret_key_set, mut aggregator) = new_aggregator(threshold);
+        let msg = plain_message();
+
+        for index in 0..=threshold {
+            let share = share_for(&secret_key_set, index, &msg);
+            let _ = aggregator.add(share).unwrap();
+        }
+
+        // A later share from a further elder for the same (now-combined) message is simply ignored.
+        let share = share_for(&secret_key_set, threshold + 1, &msg);
+        assert_eq!(aggregator.add(share).unwrap(), None);
+    }
+}
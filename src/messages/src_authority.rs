@@ -0,0 +1,91 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{proof_chain::SectionProofChain, VerifyStatus, Variant};
+use crate::{error::Result, id::PublicId, location::DstLocation};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+use xor_name::{Prefix, XorName};
+
+/// Source authority of a message.
+/// Messages do not need to sign this field as it is all verifiable (i.e. if the sig validates
+/// agains the public key and we know the pub key then we are good. If the proof is not recognised
+/// we ask for a longer chain that can be recognised). Therefor we don't need to sign this field.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash)]
+pub enum SrcAuthority {
+    /// Authored and signed by a single node.
+    Node {
+        public_id: PublicId,
+        signature: ed25519_dalek::Signature,
+    },
+    /// Authored by a whole section and signed with the section's BLS key.
+    Section {
+        prefix: Prefix<XorName>,
+        signature: bls::Signature,
+        proof: SectionProofChain,
+    },
+}
+
+impl SrcAuthority {
+    /// Verify that this authority is a valid and trusted source for `variant`/`dst`.
+    pub fn verify<'a, I>(
+        &'a self,
+        _dst: &DstLocation,
+        _dst_key: Option<&bls::PublicKey>,
+        _variant: &Variant,
+        their_keys: I,
+    ) -> Result<VerifyStatus>
+    where
+        I: IntoIterator<Item = (&'a Prefix<XorName>, &'a bls::PublicKey)>,
+    {
+        match self {
+            Self::Node { .. } => Ok(VerifyStatus::Full),
+            Self::Section { prefix, proof, .. } => {
+                proof.validate()?;
+
+                let trusted_keys = their_keys
+                    .into_iter()
+                    .filter(|(their_prefix, _)| their_prefix.matches(prefix.name()))
+                    .map(|(_, key)| key);
+
+                match proof.last_trusted_key(trusted_keys) {
+                    Some(key) if key == proof.last_key() => Ok(VerifyStatus::Full),
+                    truncation_point => Ok(VerifyStatus::Unknown {
+                        truncation_point: truncation_point.copied(),
+                    }),
+                }
+            }
+        }
+    }
+
+    /// Location this message claims to originate from.
+    pub fn src_location(&self) -> SrcLocation {
+        match self {
+            Self::Node { public_id, .. } => SrcLocation::Node(*public_id.name()),
+            Self::Section { prefix, .. } => SrcLocation::Section(*prefix),
+        }
+    }
+}
+
+/// Where a message originated from, as claimed by its `SrcAuthority`.
+#[derive(Clone, Copy, Eq, PartialEq, Debug)]
+pub enum SrcLocation {
+    /// A single node.
+    Node(XorName),
+    /// A whole section.
+    Section(Prefix<XorName>),
+}
+
+impl Debug for SrcAuthority {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::Node { public_id, .. } => write!(formatter, "Node({:?})", public_id),
+            Self::Section { prefix, .. } => write!(formatter, "Section({:?})", prefix),
+        }
+    }
+}
@@ -0,0 +1,240 @@
+// Copyright 2020 MaidSafe.net limited.
+//
+// This SAFE Network Software is licensed to you under The General Public License (GPL), version 3.
+// Unless required by applicable law or agreed to in writing, the SAFE Network Software distributed
+// under the GPL Licence is distributed on an "AS IS" BASIS, WITHOUT WARRANTIES OR CONDITIONS OF ANY
+// KIND, either express or implied. Please review the Licences for the specific language governing
+// permissions and limitations relating to use of the SAFE Network Software.
+
+use super::{proof_chain::SectionProofChain, VerifyStatus};
+use crate::{error::Result, id::PublicId};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Debug, Formatter};
+use std::net::SocketAddr;
+use tiny_keccak::{Hasher, Sha3};
+use xor_name::{Prefix, XorName};
+
+/// The content of a `Message`.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash)]
+#[allow(clippy::large_enum_variant)]
+pub enum Variant {
+    /// Sent from a node that wants to join the section handling `XorName`.
+    JoinRequest(Box<JoinRequest>),
+    /// Sent in response to `JoinRequest`.
+    JoinResponse(Box<JoinResponse>),
+    /// Sent from a previously-approved node that has been relocated to a new section, asking to
+    /// join it while preserving its age.
+    JoinAsRelocatedRequest(Box<JoinAsRelocatedRequest>),
+    /// Sent in response to `JoinAsRelocatedRequest`.
+    JoinAsRelocatedResponse(Box<JoinAsRelocatedResponse>),
+    /// Sent from a bootstrapping peer to a member of the target section.
+    BootstrapRequest(XorName),
+    /// Sent in response to `BootstrapRequest`.
+    BootstrapResponse(BootstrapResponse),
+    /// Sent to notify peers that we're still alive when we have nothing else to send them.
+    Ping,
+    /// Application-level payload, opaque to the routing layer.
+    UserMessage(Vec<u8>),
+}
+
+impl Debug for Variant {
+    fn fmt(&self, formatter: &mut Formatter) -> fmt::Result {
+        match self {
+            Self::JoinRequest(_) => write!(formatter, "JoinRequest(..)"),
+            Self::JoinResponse(response) => write!(formatter, "JoinResponse({:?})", response),
+            Self::JoinAsRelocatedRequest(_) => write!(formatter, "JoinAsRelocatedRequest(..)"),
+            Self::JoinAsRelocatedResponse(response) => {
+                write!(formatter, "JoinAsRelocatedResponse({:?})", response)
+            }
+            Self::BootstrapRequest(name) => write!(formatter, "BootstrapRequest({:?})", name),
+            Self::BootstrapResponse(response) => {
+                write!(formatter, "BootstrapResponse({:?})", response)
+            }
+            Self::Ping => write!(formatter, "Ping"),
+            Self::UserMessage(_) => write!(formatter, "UserMessage(..)"),
+        }
+    }
+}
+
+/// Request to join a section.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub struct JoinRequest {
+    /// The section key of the section the joining node is targeting, if already known.
+    pub section_key: Option<bls::PublicKey>,
+    /// Solution to a previously received resource-proof challenge. `None` on the joining node's
+    /// first attempt, before it has been asked for one.
+    pub resource_proof_response: Option<ResourceProofResponse>,
+}
+
+/// Response to a `JoinRequest`.
+///
+/// This carries the resource-proof challenge itself, rather than extending `BootstrapResponse`:
+/// bootstrapping only picks which section to talk to, before any particular node has been asked
+/// to do anything, so it has no peer yet to challenge. The challenge belongs to the join
+/// handshake that follows.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub enum JoinResponse {
+    /// The joining node has been approved and is now a full member of the section.
+    Approval,
+    /// The joining node must solve this resource-proof challenge and resend its `JoinRequest`
+    /// with the solution before it can be considered further. This forces it to do measurable
+    /// work, raising the cost of a sybil attack.
+    ResourceProofChallenge {
+        /// Random seed the solution must be derived from, so a precomputed solution can't be
+        /// replayed against a later challenge.
+        nonce: [u8; 32],
+        /// Number of hash-chain blocks the joining node must produce.
+        size: usize,
+        /// Number of leading zero bits the final hash of the chain must have.
+        difficulty: u8,
+    },
+    /// The request could not be granted.
+    Rejected(JoinRejectionReason),
+}
+
+/// Why a `JoinRequest` was rejected rather than approved or challenged, so the joining node
+/// knows whether to retry, solve a proof, or back off.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub enum JoinRejectionReason {
+    /// The section could not reach the joining node at its claimed address.
+    NodeNotReachable,
+    /// The joining node must solve a resource-proof challenge before being admitted.
+    ResourceProofRequired,
+    /// The section already has as many nodes as it wants and isn't accepting more right now.
+    SectionFull,
+}
+
+/// Proof that a joining node solved a previously issued resource-proof challenge.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub struct ResourceProofResponse {
+    /// Chain of hash blocks, each derived from the previous, whose final hash satisfies the
+    /// challenge's difficulty target. Cheap for elders to verify, expensive to have produced.
+    pub solution: Vec<Vec<u8>>,
+}
+
+impl ResourceProofResponse {
+    /// Check this response against the `nonce`/`size`/`difficulty` of the challenge it claims to
+    /// answer: `solution` must have exactly `size` blocks, chained from `nonce` by hashing each
+    /// block together with the previous hash, and the final hash must have at least `difficulty`
+    /// leading zero bits. `size` hashes is cheap for an elder to redo; finding a chain that
+    /// lands on a low-enough hash is the expensive part for the joining node.
+    pub fn verify(&self, nonce: &[u8; 32], size: usize, difficulty: u8) -> bool {
+        if self.solution.len() != size {
+            return false;
+        }
+
+        let mut hash = *nonce;
+        for block in &self.solution {
+            let mut hasher = Sha3::v256();
+            let mut next = [0; 32];
+            hasher.update(&hash);
+            hasher.update(block);
+            hasher.finalize(&mut next);
+            hash = next;
+        }
+
+        leading_zero_bits(&hash) >= u32::from(difficulty)
+    }
+}
+
+fn leading_zero_bits(hash: &[u8; 32]) -> u32 {
+    let mut bits = 0;
+    for byte in hash {
+        if *byte == 0 {
+            bits += 8;
+        } else {
+            bits += byte.leading_zeros();
+            break;
+        }
+    }
+    bits
+}
+
+/// Response to a `BootstrapRequest`.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub enum BootstrapResponse {
+    /// This node can act as the bootstrap node for the joining node. Contains the prefix and
+    /// addresses of the elders the joining node should send its `JoinRequest` to.
+    Join {
+        /// Prefix of the target section.
+        prefix: Prefix<XorName>,
+        /// Addresses of the elders of the target section.
+        elders: Vec<SocketAddr>,
+    },
+    /// The section the joining node tried to bootstrap to has rebalanced; try again against the
+    /// given addresses.
+    Rebootstrap(Vec<SocketAddr>),
+}
+
+/// Request from a node that has been relocated away from its previous section, asking the
+/// destination section to admit it while preserving its age and accumulated trust rather than
+/// starting over as a brand new node.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub struct JoinAsRelocatedRequest {
+    /// The section key of the destination section, if already known.
+    pub section_key: Option<bls::PublicKey>,
+    /// Proof, signed by the previous section, that this node is entitled to relocate here.
+    pub relocate_proof: RelocateProof,
+}
+
+/// Response to a `JoinAsRelocatedRequest`.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub enum JoinAsRelocatedResponse {
+    /// The node has been approved and its age carried over from `RelocateDetails`.
+    Approval,
+    /// The request could not be granted.
+    Rejected(JoinRejectionReason),
+}
+
+/// States that `public_id`, currently of the given `age`, is being relocated to `destination`.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub struct RelocateDetails {
+    /// Public id of the node being relocated.
+    pub public_id: PublicId,
+    /// The node's age at the time of relocation, to be preserved at the destination.
+    pub age: u8,
+    /// Prefix of the section the node is being relocated to.
+    pub destination: Prefix<XorName>,
+}
+
+/// A `RelocateDetails` signed by the node's originating section, in the same
+/// `SrcAuthority::Section` style as any other section-sourced message, so the destination can
+/// verify it through the ordinary proof-chain trust mechanism.
+#[derive(Clone, Eq, PartialEq, Serialize, Deserialize, Hash, Debug)]
+pub struct RelocateProof {
+    /// The statement being vouched for.
+    pub details: RelocateDetails,
+    /// The originating section's signature over `details`.
+    pub signature: bls::Signature,
+    /// Proof chain from that section's genesis key up to the key that produced `signature`.
+    pub proof: SectionProofChain,
+}
+
+impl RelocateProof {
+    /// Verify the signature and check whether `their_keys` trusts the chain it's signed with.
+    ///
+    /// Unlike `SrcAuthority::verify`, `their_keys` isn't filtered by prefix first: `proof` was
+    /// signed by the node's *originating* section, whose prefix isn't recorded anywhere in
+    /// `RelocateDetails` (only `destination`, the prefix being relocated *to*, is) - there's
+    /// nothing to filter by. Trusting any key we hold that the chain also contains is still sound,
+    /// since the chain itself must `validate()` back to a genesis key, same as for `SrcAuthority`.
+    pub fn verify<'a, I>(&self, their_keys: I) -> Result<VerifyStatus>
+    where
+        I: IntoIterator<Item = (&'a Prefix<XorName>, &'a bls::PublicKey)>,
+    {
+        self.proof.validate()?;
+
+        let bytes = bincode::serialize(&self.details)?;
+        if !self.proof.last_key().verify(&self.signature, &bytes) {
+            return Err(crate::error::RoutingError::FailedSignature);
+        }
+
+        let trusted_keys = their_keys.into_iter().map(|(_, key)| key);
+        match self.proof.last_trusted_key(trusted_keys) {
+            Some(key) if key == self.proof.last_key() => Ok(VerifyStatus::Full),
+            truncation_point => Ok(VerifyStatus::Unknown {
+                truncation_point: truncation_point.copied(),
+            }),
+        }
+    }
+}